@@ -0,0 +1,326 @@
+use crate::disasm::StringEncoding;
+use crate::param::{self, ParamKind};
+use byteorder::{LittleEndian, WriteBytesExt};
+use hash40::{Hash40, WriteHash40};
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
+
+// Re-encodes a string the same way `disasm::decode_string` reads it back,
+// so a disassemble/assemble round trip preserves the original bytes. Errors
+// instead of silently truncating/mangling a string the chosen encoding
+// can't represent.
+fn encode_string(string: &str, encoding: StringEncoding) -> Result<Vec<u8>, Error> {
+    match encoding {
+        StringEncoding::Utf8 | StringEncoding::Utf8Lossy => Ok(string.as_bytes().to_vec()),
+        StringEncoding::Latin1 => string
+            .chars()
+            .map(|c| {
+                u8::try_from(c as u32).map_err(|_| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!("{:?} is not representable in Latin-1", c),
+                    )
+                })
+            })
+            .collect(),
+        StringEncoding::ShiftJis => {
+            let (encoded, _, had_errors) = encoding_rs::SHIFT_JIS.encode(string);
+            if had_errors {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("{:?} is not representable in Shift-JIS", string),
+                ));
+            }
+            Ok(encoded.into_owned())
+        }
+    }
+}
+
+/// Bookkeeping shared by both assembly passes.
+///
+/// The hash table and ref section are built up as distinct values are
+/// encountered; both are keyed so that the second pass can dedupe the way
+/// `disassemble` does (a `RefTable` reader groups struct members by
+/// `refpos`, so two structs with identical `(hash_index, offset)` layouts
+/// must end up pointing at the same ref-table entry here too).
+struct BuildData {
+    hash_indices: HashMap<Hash40, u32>,
+    hash_table: Vec<Hash40>,
+    string_encoding: StringEncoding,
+    string_offsets: HashMap<Vec<u8>, u32>,
+    ref_tables: HashMap<Vec<(u32, u32)>, u32>,
+    ref_section: Vec<u8>,
+}
+
+impl BuildData {
+    fn new(string_encoding: StringEncoding) -> Self {
+        Self {
+            hash_indices: HashMap::new(),
+            hash_table: Vec::new(),
+            string_encoding,
+            string_offsets: HashMap::new(),
+            ref_tables: HashMap::new(),
+            ref_section: Vec::new(),
+        }
+    }
+
+    fn hash_index(&mut self, hash: Hash40) -> u32 {
+        if let Some(&index) = self.hash_indices.get(&hash) {
+            return index;
+        }
+
+        let index = self.hash_table.len() as u32;
+        self.hash_table.push(hash);
+        self.hash_indices.insert(hash, index);
+        index
+    }
+
+    fn string_offset(&mut self, string: &str) -> Result<u32, Error> {
+        let bytes = encode_string(string, self.string_encoding)?;
+        if let Some(&offset) = self.string_offsets.get(&bytes) {
+            return Ok(offset);
+        }
+
+        let offset = self.ref_section.len() as u32;
+        self.ref_section.extend_from_slice(&bytes);
+        self.ref_section.push(0);
+        self.string_offsets.insert(bytes, offset);
+        Ok(offset)
+    }
+
+    // `table` is already sorted by hash index, the order `disassemble` sorts each ref table into.
+    fn ref_table_offset(&mut self, table: Vec<(u32, u32)>) -> u32 {
+        if let Some(&offset) = self.ref_tables.get(&table) {
+            return offset;
+        }
+
+        let offset = self.ref_section.len() as u32;
+        for &(hash_index, param_offset) in &table {
+            self.ref_section
+                .write_u32::<LittleEndian>(hash_index)
+                .unwrap();
+            self.ref_section
+                .write_u32::<LittleEndian>(param_offset)
+                .unwrap();
+        }
+        self.ref_tables.insert(table, offset);
+        offset
+    }
+}
+
+/// Serialize a `ParamKind` tree back into the binary `.prc` format, the
+/// inverse of [`disassemble`](crate::disasm::disassemble).
+pub fn assemble(root: &param::ParamKind) -> Result<Vec<u8>, Error> {
+    assemble_with_encoding(root, StringEncoding::Utf8)
+}
+
+/// Like [`assemble`], but re-encoding `Str` params with `string_encoding`
+/// instead of assuming UTF-8 - use the same encoding the source file was
+/// disassembled with to get a byte-identical round trip.
+///
+/// Errors if any `Str` param isn't representable in `string_encoding`,
+/// rather than writing a silently truncated or corrupted string.
+pub fn assemble_with_encoding(
+    root: &param::ParamKind,
+    string_encoding: StringEncoding,
+) -> Result<Vec<u8>, Error> {
+    let mut bd = BuildData::new(string_encoding);
+    collect_hashes(root, &mut bd);
+
+    let mut params = Vec::new();
+    emit_param(root, &mut params, &mut bd)?;
+
+    let hashsize = (bd.hash_table.len() * 8) as u32;
+    let refsize = bd.ref_section.len() as u32;
+
+    let mut out = Vec::with_capacity(0x10 + hashsize as usize + refsize as usize + params.len());
+    out.extend_from_slice(param::MAGIC);
+    out.write_u32::<LittleEndian>(hashsize).unwrap();
+    out.write_u32::<LittleEndian>(refsize).unwrap();
+    for hash in &bd.hash_table {
+        out.write_hash40::<LittleEndian>(*hash).unwrap();
+    }
+    out.extend_from_slice(&bd.ref_section);
+    out.extend_from_slice(&params);
+
+    Ok(out)
+}
+
+// First pass: every `Hash` value and every struct member key needs a slot in
+// the hash table before we can write offsets into it.
+fn collect_hashes(param: &ParamKind, bd: &mut BuildData) {
+    match param {
+        ParamKind::Hash(hash) => {
+            bd.hash_index(*hash);
+        }
+        ParamKind::List(list) => {
+            for child in list {
+                collect_hashes(child, bd);
+            }
+        }
+        ParamKind::Struct(fields) => {
+            for (hash, child) in fields {
+                bd.hash_index(*hash);
+                collect_hashes(child, bd);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Second pass: write each param's tag + payload into `out`, appending
+// children right after their container's header so offsets stay relative to
+// the container's own position, the same scheme `disassemble` reads back.
+fn emit_param(param: &ParamKind, out: &mut Vec<u8>, bd: &mut BuildData) -> Result<(), Error> {
+    match param {
+        ParamKind::Bool(val) => {
+            out.push(1);
+            out.push(*val as u8);
+        }
+        ParamKind::I8(val) => {
+            out.push(2);
+            out.write_i8(*val).unwrap();
+        }
+        ParamKind::U8(val) => {
+            out.push(3);
+            out.push(*val);
+        }
+        ParamKind::I16(val) => {
+            out.push(4);
+            out.write_i16::<LittleEndian>(*val).unwrap();
+        }
+        ParamKind::U16(val) => {
+            out.push(5);
+            out.write_u16::<LittleEndian>(*val).unwrap();
+        }
+        ParamKind::I32(val) => {
+            out.push(6);
+            out.write_i32::<LittleEndian>(*val).unwrap();
+        }
+        ParamKind::U32(val) => {
+            out.push(7);
+            out.write_u32::<LittleEndian>(*val).unwrap();
+        }
+        ParamKind::Float(val) => {
+            out.push(8);
+            out.write_f32::<LittleEndian>(*val).unwrap();
+        }
+        ParamKind::Hash(hash) => {
+            out.push(9);
+            out.write_i32::<LittleEndian>(bd.hash_index(*hash) as i32)
+                .unwrap();
+        }
+        ParamKind::Str(val) => {
+            out.push(10);
+            out.write_u32::<LittleEndian>(bd.string_offset(val)?).unwrap();
+        }
+        ParamKind::List(list) => {
+            let pos = out.len() as u32;
+            out.push(11);
+            out.write_u32::<LittleEndian>(list.len() as u32).unwrap();
+
+            let offsets_at = out.len();
+            out.resize(offsets_at + list.len() * 4, 0);
+
+            for (i, child) in list.iter().enumerate() {
+                let child_pos = out.len() as u32;
+                (&mut out[offsets_at + i * 4..offsets_at + i * 4 + 4])
+                    .write_u32::<LittleEndian>(child_pos - pos)
+                    .unwrap();
+                emit_param(child, out, bd)?;
+            }
+        }
+        ParamKind::Struct(fields) => {
+            let pos = out.len() as u32;
+            out.push(12);
+            out.write_u32::<LittleEndian>(fields.len() as u32).unwrap();
+
+            let refpos_at = out.len();
+            out.write_u32::<LittleEndian>(0).unwrap();
+
+            let mut table = Vec::with_capacity(fields.len());
+            for (hash, child) in fields {
+                let child_pos = out.len() as u32;
+                table.push((bd.hash_index(*hash), child_pos - pos));
+                emit_param(child, out, bd)?;
+            }
+            table.sort_by_key(|&(hash_index, _)| hash_index);
+
+            let refpos = bd.ref_table_offset(table);
+            (&mut out[refpos_at..refpos_at + 4])
+                .write_u32::<LittleEndian>(refpos)
+                .unwrap();
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disasm::disassemble;
+    use std::io::Cursor;
+    use std::str::FromStr;
+
+    #[test]
+    fn round_trips_through_disassemble() {
+        let root = ParamKind::Struct(vec![
+            (Hash40::from_str("0x1").unwrap(), ParamKind::I32(42)),
+            (
+                Hash40::from_str("0x2").unwrap(),
+                ParamKind::List(vec![
+                    ParamKind::Bool(true),
+                    ParamKind::Str("hello".to_string()),
+                ]),
+            ),
+            (
+                // Shares its (hash_index, offset) layout with no other struct here,
+                // but exercises the nested-struct ref-table path regardless.
+                Hash40::from_str("0x3").unwrap(),
+                ParamKind::Struct(vec![(Hash40::from_str("0x1").unwrap(), ParamKind::Float(1.5))]),
+            ),
+        ]);
+
+        let bytes = assemble(&root).unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let roundtripped = disassemble(&mut cursor).unwrap();
+
+        assert_eq!(roundtripped, root);
+    }
+
+    #[test]
+    fn dedupes_identical_struct_ref_tables() {
+        // Two structs with the same single (hash, value-kind) shape should
+        // share one ref-table entry in the ref section.
+        let member = |n: i32| ParamKind::Struct(vec![(Hash40::from_str("0x1").unwrap(), ParamKind::I32(n))]);
+        let root = ParamKind::Struct(vec![
+            (Hash40::from_str("0x10").unwrap(), member(1)),
+            (Hash40::from_str("0x11").unwrap(), member(2)),
+        ]);
+
+        let bytes = assemble(&root).unwrap();
+
+        // Both members share one 8-byte ref table and the root's two-entry
+        // table adds 16 more: 24 bytes, where no dedup would give 32.
+        let refsize = u32::from_le_bytes([bytes[12], bytes[13], bytes[14], bytes[15]]);
+        assert_eq!(refsize, 24);
+
+        let mut cursor = Cursor::new(bytes);
+        let roundtripped = disassemble(&mut cursor).unwrap();
+
+        assert_eq!(roundtripped, root);
+    }
+
+    #[test]
+    fn rejects_strings_not_representable_in_the_chosen_encoding() {
+        // "テスト" isn't representable in Latin-1; this must error rather
+        // than silently truncate each codepoint down to a stray byte.
+        let root = ParamKind::Struct(vec![(
+            Hash40::from_str("0x1").unwrap(),
+            ParamKind::Str("テスト".to_string()),
+        )]);
+
+        assert!(assemble_with_encoding(&root, StringEncoding::Latin1).is_err());
+    }
+}
@@ -17,20 +17,188 @@ struct FileData {
 // (hash index, param offset)
 struct RefTable(Vec<(u32, u32)>);
 
+/// Maximum container nesting depth [`disassemble`] will follow before
+/// giving up, guarding against maliciously crafted offset graphs.
+pub const DEFAULT_MAX_DEPTH: usize = 512;
+
+/// How to decode the raw, null-terminated bytes of a `Str` param.
+///
+/// `.prc` files in the wild are usually plain ASCII, but some tools have
+/// written Shift-JIS, and nothing stops a string param from holding
+/// arbitrary UTF-8 text, so the decoding strategy is a choice rather than
+/// a fixed behavior.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StringEncoding {
+    /// Reject strings that aren't valid UTF-8.
+    Utf8,
+    /// Replace invalid UTF-8 sequences with `U+FFFD`.
+    Utf8Lossy,
+    /// Every byte maps directly to the codepoint of the same value.
+    Latin1,
+    /// Shift-JIS, as emitted by some older Smash modding tools.
+    ShiftJis,
+}
+
+/// Options controlling how [`disassemble_streaming`] reads a param file.
+#[derive(Debug, Clone, Copy)]
+pub struct ReaderConfig {
+    pub string_encoding: StringEncoding,
+    pub max_depth: usize,
+}
+
+impl Default for ReaderConfig {
+    fn default() -> Self {
+        Self {
+            string_encoding: StringEncoding::Utf8,
+            max_depth: DEFAULT_MAX_DEPTH,
+        }
+    }
+}
+
+fn decode_string(bytes: Vec<u8>, encoding: StringEncoding) -> Result<String, Error> {
+    match encoding {
+        StringEncoding::Utf8 => {
+            String::from_utf8(bytes).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+        }
+        StringEncoding::Utf8Lossy => Ok(String::from_utf8_lossy(&bytes).into_owned()),
+        StringEncoding::Latin1 => Ok(bytes.into_iter().map(|b| b as char).collect()),
+        StringEncoding::ShiftJis => {
+            let (decoded, _, had_errors) = encoding_rs::SHIFT_JIS.decode(&bytes);
+            if had_errors {
+                return Err(Error::new(ErrorKind::InvalidData, "invalid Shift-JIS string"));
+            }
+            Ok(decoded.into_owned())
+        }
+    }
+}
+
+fn checked_hash(fd: &FileData, index: usize) -> Result<Hash40, Error> {
+    fd.hash_table
+        .get(index)
+        .copied()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "hash table index out of range"))
+}
+
+// Validates that `base + offset` both fits in a u64 and lands inside the
+// file before the caller seeks there.
+fn checked_position(file_len: u64, base: u64, offset: u32) -> Result<u64, Error> {
+    let pos = base
+        .checked_add(offset as u64)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "offset overflowed file position"))?;
+    if pos >= file_len {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "offset points outside of the file",
+        ));
+    }
+    Ok(pos)
+}
+
 pub fn disassemble(cursor: &mut Cursor<Vec<u8>>) -> Result<param::ParamKind, Error> {
+    disassemble_with_config(cursor, &ReaderConfig::default())
+}
+
+pub fn disassemble_with_config(
+    cursor: &mut Cursor<Vec<u8>>,
+    config: &ReaderConfig,
+) -> Result<param::ParamKind, Error> {
+    let mut builder = TreeBuilder::new();
+    disassemble_streaming(cursor, &mut builder, config)?;
+    builder
+        .into_inner()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "param file does not contain a root"))
+}
+
+/// Callbacks driven by [`disassemble_streaming`] as it walks a binary param
+/// file, without ever materializing the whole tree at once.
+pub trait ParamVisitor {
+    /// A struct with `size` members is being entered; its members follow as
+    /// `size` `struct_key`-then-value pairs, then a matching `end_container`.
+    fn begin_struct(&mut self, size: usize);
+    /// The key for the struct member about to be visited.
+    fn struct_key(&mut self, hash: Hash40);
+    /// A list with `size` elements is being entered; its elements follow as
+    /// `size` values, then a matching `end_container`.
+    fn begin_list(&mut self, size: usize);
+    /// The container most recently begun (via `begin_struct`/`begin_list`)
+    /// has no more members/elements.
+    fn end_container(&mut self);
+    /// A leaf value, i.e. anything other than `List`/`Struct`.
+    fn scalar(&mut self, value: param::ParamKind);
+}
+
+// One entry per open container; tracks where its children live in the file
+// so the driver loop can pull the next one without recursing.
+enum Frame {
+    List {
+        children: std::vec::IntoIter<u64>,
+    },
+    Struct {
+        children: std::vec::IntoIter<(Hash40, u64)>,
+    },
+}
+
+impl Frame {
+    fn next_child(&mut self) -> Option<(u64, Option<Hash40>)> {
+        match self {
+            Frame::List { children } => children.next().map(|pos| (pos, None)),
+            Frame::Struct { children } => children.next().map(|(hash, pos)| (pos, Some(hash))),
+        }
+    }
+}
+
+/// Walk a binary param file, driving `visitor` instead of building a tree.
+///
+/// Containers are tracked with an explicit stack rather than recursive
+/// calls, so this is the primitive [`disassemble`] is built on top of via
+/// [`TreeBuilder`]; reach for it directly to extract a single field, count
+/// nodes, or transcode straight to another format without building the
+/// whole `ParamKind` tree in memory.
+pub fn disassemble_streaming<V: ParamVisitor>(
+    cursor: &mut Cursor<Vec<u8>>,
+    visitor: &mut V,
+    config: &ReaderConfig,
+) -> Result<(), Error> {
+    let file_len = cursor.get_ref().len() as u64;
+
     let mut magic_bytes = [0; 8];
-    cursor.read(&mut magic_bytes)?;
+    cursor.read_exact(&mut magic_bytes)?;
     if &magic_bytes != param::MAGIC {
         return Err(Error::new(ErrorKind::InvalidData, "Invalid file magic"));
     }
 
     let hashsize = cursor.read_u32::<LittleEndian>()?;
-    let hashnum = (hashsize / 8) as usize;
     let refsize = cursor.read_u32::<LittleEndian>()?;
 
+    // Widen to u64 before adding so a maliciously large hashsize/refsize
+    // can't wrap the header math instead of being caught as bad data.
+    let ref_start = 0x10u64
+        .checked_add(hashsize as u64)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "hashsize overflowed the header"))?;
+    let param_start = ref_start
+        .checked_add(refsize as u64)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "refsize overflowed the header"))?;
+    if param_start >= file_len {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "hashsize/refsize place the param section outside of the file",
+        ));
+    }
+
+    // Bound the hash table length against what's actually left in the file
+    // before trusting it to size an allocation.
+    let hashnum = (hashsize / 8) as usize;
+    let max_hashnum = ((file_len - 0x10) / 8) as usize;
+    if hashnum > max_hashnum {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "hashsize claims more entries than the file can hold",
+        ));
+    }
+
     let mut fd = FileData {
-        ref_start: 0x10 + hashsize,
-        param_start: 0x10 + hashsize + refsize,
+        ref_start: ref_start as u32,
+        param_start: param_start as u32,
         hash_table: Vec::with_capacity(hashnum),
         ref_tables: HashMap::new(),
     };
@@ -49,119 +217,434 @@ pub fn disassemble(cursor: &mut Cursor<Vec<u8>>) -> Result<param::ParamKind, Err
     }
     cursor.set_position(cursor.position() - 1);
 
-    read_param(cursor, &mut fd)
+    let mut stack: Vec<Frame> = Vec::new();
+    visit_one(cursor, &mut fd, visitor, &mut stack, file_len, config)?;
+
+    while let Some(frame) = stack.last_mut() {
+        match frame.next_child() {
+            Some((child_pos, key)) => {
+                if let Some(hash) = key {
+                    visitor.struct_key(hash);
+                }
+                cursor.set_position(child_pos);
+                visit_one(cursor, &mut fd, visitor, &mut stack, file_len, config)?;
+            }
+            None => {
+                stack.pop();
+                visitor.end_container();
+            }
+        }
+    }
+
+    Ok(())
 }
 
-fn read_param(cursor: &mut Cursor<Vec<u8>>, fd: &mut FileData) -> Result<param::ParamKind, Error> {
+// Reads exactly one param at the cursor's current position: scalars are
+// reported to the visitor directly, containers push a `Frame` so their
+// children get visited by the driver loop above instead of by recursing.
+fn visit_one<V: ParamVisitor>(
+    cursor: &mut Cursor<Vec<u8>>,
+    fd: &mut FileData,
+    visitor: &mut V,
+    stack: &mut Vec<Frame>,
+    file_len: u64,
+    config: &ReaderConfig,
+) -> Result<(), Error> {
+    let pos = cursor.position();
     match cursor.read_u8()? {
         1 => {
             let val = cursor.read_u8()?;
-            Ok(param::ParamKind::Bool(val != 0))
+            visitor.scalar(param::ParamKind::Bool(val != 0));
         }
         2 => {
             let val = cursor.read_i8()?;
-            Ok(param::ParamKind::I8(val))
+            visitor.scalar(param::ParamKind::I8(val));
         }
         3 => {
             let val = cursor.read_u8()?;
-            Ok(param::ParamKind::U8(val))
+            visitor.scalar(param::ParamKind::U8(val));
         }
         4 => {
             let val = cursor.read_i16::<LittleEndian>()?;
-            Ok(param::ParamKind::I16(val))
+            visitor.scalar(param::ParamKind::I16(val));
         }
         5 => {
             let val = cursor.read_u16::<LittleEndian>()?;
-            Ok(param::ParamKind::U16(val))
+            visitor.scalar(param::ParamKind::U16(val));
         }
         6 => {
             let val = cursor.read_i32::<LittleEndian>()?;
-            Ok(param::ParamKind::I32(val))
+            visitor.scalar(param::ParamKind::I32(val));
         }
         7 => {
             let val = cursor.read_u32::<LittleEndian>()?;
-            Ok(param::ParamKind::U32(val))
+            visitor.scalar(param::ParamKind::U32(val));
         }
         8 => {
             let val = cursor.read_f32::<LittleEndian>()?;
-            Ok(param::ParamKind::Float(val))
+            visitor.scalar(param::ParamKind::Float(val));
         }
         9 => {
-            let val = fd.hash_table[cursor.read_i32::<LittleEndian>()? as usize];
-            Ok(param::ParamKind::Hash(val))
+            let index = cursor.read_i32::<LittleEndian>()? as usize;
+            visitor.scalar(param::ParamKind::Hash(checked_hash(fd, index)?));
         }
         10 => {
             let strpos = cursor.read_u32::<LittleEndian>()?;
-            //remembering where we were is actually unnecessary
-            //let curpos = cursor.position();
-            cursor.set_position((fd.ref_start + strpos) as u64);
-            let mut val = String::new();
-            let mut next: u8;
+            cursor.set_position(checked_position(file_len, fd.ref_start as u64, strpos)?);
+            let mut bytes = Vec::new();
             loop {
-                next = cursor.read_u8()?;
+                let next = cursor.read_u8()?;
                 if next != 0 {
-                    val.push(next as char);
+                    bytes.push(next);
                 } else {
                     break;
                 }
             }
-            //cursor.set_position(curpos);
-            Ok(param::ParamKind::Str(val))
+            visitor.scalar(param::ParamKind::Str(decode_string(bytes, config.string_encoding)?));
         }
         11 => {
-            let pos = cursor.position() - 1;
+            if stack.len() >= config.max_depth {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "exceeded maximum param nesting depth",
+                ));
+            }
+
             let size = cursor.read_u32::<LittleEndian>()?;
 
-            let params = (0..size)
-                    .map(|_| cursor.read_u32::<LittleEndian>())
-                    .collect::<Result<Vec<_>, _>>()?
-                    .into_iter()
-                    .map(|offset|{
-                        cursor.set_position(pos + offset as u64);
-                        read_param(cursor, fd)
-                    })
-                    .collect::<Result<Vec<_>, _>>()?;
+            // Each element contributes a 4-byte offset right here; a size
+            // that couldn't possibly fit in what's left of the file is bad
+            // data, not a reason to try allocating it.
+            let remaining = file_len.saturating_sub(cursor.position());
+            if (size as u64) > remaining / 4 {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "list size exceeds bytes remaining in the file",
+                ));
+            }
+
+            let children = (0..size)
+                .map(|_| {
+                    cursor
+                        .read_u32::<LittleEndian>()
+                        .and_then(|offset| checked_position(file_len, pos, offset))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
 
-            Ok(param::ParamKind::List(params))
+            visitor.begin_list(size as usize);
+            stack.push(Frame::List {
+                children: children.into_iter(),
+            });
         }
         12 => {
-            let pos = cursor.position() - 1;
-            let size = cursor.read_u32::<LittleEndian>().unwrap() as usize;
-            let refpos = cursor.read_u32::<LittleEndian>().unwrap();
+            if stack.len() >= config.max_depth {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "exceeded maximum param nesting depth",
+                ));
+            }
+
+            let size = cursor.read_u32::<LittleEndian>()? as usize;
+            let refpos = cursor.read_u32::<LittleEndian>()?;
+
+            // Each member contributes an 8-byte (hash index, offset) entry
+            // to the ref table; bound `size` against what's left there
+            // before it's trusted to size an allocation.
+            let table_pos = checked_position(file_len, fd.ref_start as u64, refpos)?;
+            let remaining = file_len.saturating_sub(table_pos);
+            if (size as u64) > remaining / 8 {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "struct size exceeds bytes remaining in the ref section",
+                ));
+            }
 
             if !fd.ref_tables.contains_key(&refpos) {
-                cursor.set_position((fd.ref_start + refpos) as u64);
+                cursor.set_position(table_pos);
                 let mut new_table = (0..size)
-                                    .map(|_|(
-                                        cursor.read_u32::<LittleEndian>().unwrap(),
-                                        cursor.read_u32::<LittleEndian>().unwrap()
-                                    ))
-                                    .collect::<Vec<_>>();
+                    .map(|_| {
+                        Ok((
+                            cursor.read_u32::<LittleEndian>()?,
+                            cursor.read_u32::<LittleEndian>()?,
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
                 new_table.sort_by_key(|a| a.0);
                 fd.ref_tables.insert(refpos, RefTable(new_table));
             }
 
-            let &RefTable(ref table) = fd.ref_tables.get(&refpos).unwrap();
+            let RefTable(table) = fd.ref_tables.get(&refpos).unwrap();
 
-            let params = table.iter()
-                .map(|&(hash_index, offset)| (hash_index as usize, offset as u64))
-                .collect::<Vec<_>>()
-                .into_iter()
-                .map(|(hash_index, offset)|{
-                    let hash = fd.hash_table[hash_index];
-                    cursor.set_position(pos + offset);
-                    (hash, read_param(cursor, fd).unwrap())
+            let children = table
+                .iter()
+                .map(|&(hash_index, offset)| {
+                    let hash = checked_hash(fd, hash_index as usize)?;
+                    let child_pos = checked_position(file_len, pos, offset)?;
+                    Ok((hash, child_pos))
                 })
-                .collect::<Vec<(Hash40, param::ParamKind)>>();
+                .collect::<Result<Vec<_>, Error>>()?;
 
-            Ok(param::ParamKind::Struct(params))
+            visitor.begin_struct(size);
+            stack.push(Frame::Struct {
+                children: children.into_iter(),
+            });
         }
-        _ => Err(Error::new(
-            ErrorKind::InvalidData,
-            format!(
-                "encountered invalid param number at position: {}",
-                cursor.position() - 1
+        _ => {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("encountered invalid param number at position: {}", pos),
+            ))
+        }
+    }
+
+    Ok(())
+}
+
+/// Rebuilds a full [`param::ParamKind`] tree from a [`ParamVisitor`]
+/// callback stream; this is what [`disassemble`] uses under the hood.
+#[derive(Default)]
+pub struct TreeBuilder {
+    stack: Vec<param::ParamKind>,
+    pending_key: Vec<Option<Hash40>>,
+    root: Option<param::ParamKind>,
+}
+
+impl TreeBuilder {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Takes the finished tree, if the visited stream closed its root
+    /// container before this was called.
+    pub fn into_inner(self) -> Option<param::ParamKind> {
+        self.root
+    }
+
+    fn attach(&mut self, value: param::ParamKind) {
+        match self.stack.last_mut() {
+            Some(param::ParamKind::List(list)) => list.push(value),
+            Some(param::ParamKind::Struct(fields)) => {
+                let hash = self
+                    .pending_key
+                    .last_mut()
+                    .and_then(Option::take)
+                    .expect("struct_key must be called before each struct member");
+                fields.push((hash, value));
+            }
+            Some(_) => unreachable!("only list/struct can be open containers"),
+            None => self.root = Some(value),
+        }
+    }
+}
+
+impl ParamVisitor for TreeBuilder {
+    fn begin_struct(&mut self, size: usize) {
+        self.stack.push(param::ParamKind::Struct(Vec::with_capacity(size)));
+        self.pending_key.push(None);
+    }
+
+    fn struct_key(&mut self, hash: Hash40) {
+        *self
+            .pending_key
+            .last_mut()
+            .expect("struct_key called outside of a struct") = Some(hash);
+    }
+
+    fn begin_list(&mut self, size: usize) {
+        self.stack.push(param::ParamKind::List(Vec::with_capacity(size)));
+        self.pending_key.push(None);
+    }
+
+    fn end_container(&mut self) {
+        let value = self
+            .stack
+            .pop()
+            .expect("end_container called without a matching begin_struct/begin_list");
+        self.pending_key.pop();
+        self.attach(value);
+    }
+
+    fn scalar(&mut self, value: param::ParamKind) {
+        self.attach(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asm::assemble;
+    use byteorder::WriteBytesExt;
+    use std::str::FromStr;
+
+    fn sample() -> param::ParamKind {
+        param::ParamKind::Struct(vec![
+            (Hash40::from_str("0x1").unwrap(), param::ParamKind::I32(42)),
+            (
+                Hash40::from_str("0x2").unwrap(),
+                param::ParamKind::List(vec![param::ParamKind::Bool(true), param::ParamKind::U8(1)]),
             ),
-        )),
+        ])
+    }
+
+    // A visitor that just counts how many times each callback fires, to
+    // confirm the streaming walk visits exactly what the binary layout says
+    // it should without ever materializing a tree.
+    #[derive(Default)]
+    struct Counts {
+        begin_struct: usize,
+        begin_list: usize,
+        end_container: usize,
+        scalars: usize,
+        keys: Vec<Hash40>,
+    }
+
+    impl ParamVisitor for Counts {
+        fn begin_struct(&mut self, _size: usize) {
+            self.begin_struct += 1;
+        }
+        fn struct_key(&mut self, hash: Hash40) {
+            self.keys.push(hash);
+        }
+        fn begin_list(&mut self, _size: usize) {
+            self.begin_list += 1;
+        }
+        fn end_container(&mut self) {
+            self.end_container += 1;
+        }
+        fn scalar(&mut self, _value: param::ParamKind) {
+            self.scalars += 1;
+        }
+    }
+
+    #[test]
+    fn streaming_visits_every_node_exactly_once() {
+        let bytes = assemble(&sample()).unwrap();
+        let mut cursor = Cursor::new(bytes);
+
+        let mut counts = Counts::default();
+        disassemble_streaming(&mut cursor, &mut counts, &ReaderConfig::default()).unwrap();
+
+        assert_eq!(counts.begin_struct, 1); // just the root struct
+        assert_eq!(counts.begin_list, 1);
+        assert_eq!(counts.end_container, 2);
+        assert_eq!(counts.scalars, 3); // I32(42), Bool(true), U8(1)
+        assert_eq!(
+            counts.keys,
+            vec![Hash40::from_str("0x1").unwrap(), Hash40::from_str("0x2").unwrap()]
+        );
+    }
+
+    #[test]
+    fn tree_builder_matches_disassemble() {
+        let root = sample();
+        let bytes = assemble(&root).unwrap();
+
+        let mut cursor = Cursor::new(bytes);
+        let mut builder = TreeBuilder::new();
+        disassemble_streaming(&mut cursor, &mut builder, &ReaderConfig::default()).unwrap();
+
+        assert_eq!(builder.into_inner(), Some(root));
+    }
+
+    #[test]
+    fn truncated_file_errors_instead_of_panicking() {
+        let mut bytes = assemble(&sample()).unwrap();
+        bytes.truncate(bytes.len() - 4);
+
+        let mut cursor = Cursor::new(bytes);
+        assert!(disassemble(&mut cursor).is_err());
+    }
+
+    #[test]
+    fn out_of_range_hash_index_errors_instead_of_panicking() {
+        let mut bytes = assemble(&param::ParamKind::Struct(vec![(
+            Hash40::from_str("0x1").unwrap(),
+            param::ParamKind::Hash(Hash40::from_str("0x1").unwrap()),
+        )]))
+        .unwrap();
+
+        // Corrupt the hash index written for the `Hash` param so it points
+        // past the end of the (one-entry) hash table. That param is the last
+        // thing in the file: its tag byte followed by a 4-byte index.
+        let tag_pos = bytes.len() - 5;
+        assert_eq!(bytes[tag_pos], 9);
+        bytes[tag_pos + 1] = 0xff;
+
+        let mut cursor = Cursor::new(bytes);
+        assert!(disassemble(&mut cursor).is_err());
+    }
+
+    #[test]
+    fn exceeding_max_depth_errors_instead_of_overflowing() {
+        let mut root = param::ParamKind::Struct(vec![]);
+        for _ in 0..4 {
+            root = param::ParamKind::Struct(vec![(Hash40::from_str("0x1").unwrap(), root)]);
+        }
+        let bytes = assemble(&root).unwrap();
+
+        let mut cursor = Cursor::new(bytes);
+        let config = ReaderConfig {
+            max_depth: 2,
+            ..ReaderConfig::default()
+        };
+        let mut builder = TreeBuilder::new();
+        assert!(disassemble_streaming(&mut cursor, &mut builder, &config).is_err());
+    }
+
+    #[test]
+    fn string_encoding_round_trips_non_ascii_bytes() {
+        use crate::asm::assemble_with_encoding;
+
+        // Not valid UTF-8 on its own, but a real Shift-JIS string.
+        let root = param::ParamKind::Struct(vec![(
+            Hash40::from_str("0x1").unwrap(),
+            param::ParamKind::Str("テスト".to_string()),
+        )]);
+
+        let bytes = assemble_with_encoding(&root, StringEncoding::ShiftJis).unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let config = ReaderConfig {
+            string_encoding: StringEncoding::ShiftJis,
+            ..ReaderConfig::default()
+        };
+
+        assert_eq!(disassemble_with_config(&mut cursor, &config).unwrap(), root);
+    }
+
+    #[test]
+    fn oversized_header_fields_error_instead_of_overflowing() {
+        let mut bytes = assemble(&sample()).unwrap();
+
+        // Claim a hashsize so large that `0x10 + hashsize` would overflow a
+        // u32 if computed directly, instead of being rejected as bad data.
+        (&mut bytes[8..12]).write_u32::<LittleEndian>(0xFFFFFFF0).unwrap();
+
+        let mut cursor = Cursor::new(bytes);
+        assert!(disassemble(&mut cursor).is_err());
+    }
+
+    #[test]
+    fn oversized_container_size_errors_instead_of_over_allocating() {
+        let mut bytes = assemble(&param::ParamKind::Struct(vec![(
+            Hash40::from_str("0x1").unwrap(),
+            param::ParamKind::List(vec![param::ParamKind::Bool(true)]),
+        )]))
+        .unwrap();
+
+        // Claim the one-element list has billions of elements; each would
+        // need a 4-byte offset the (tiny) file can't possibly hold.
+        let tag_pos = bytes.iter().position(|&b| b == 11).unwrap();
+        (&mut bytes[tag_pos + 1..tag_pos + 5])
+            .write_u32::<LittleEndian>(0xFFFF_FFF0)
+            .unwrap();
+
+        let mut cursor = Cursor::new(bytes);
+        assert!(disassemble(&mut cursor).is_err());
+    }
+
+    #[test]
+    fn strict_utf8_rejects_invalid_bytes() {
+        assert!(decode_string(vec![0xff, 0xfe], StringEncoding::Utf8).is_err());
+        assert!(decode_string(vec![0xff, 0xfe], StringEncoding::Utf8Lossy).is_ok());
     }
 }
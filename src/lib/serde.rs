@@ -0,0 +1,362 @@
+//! `serde` support for [`ParamKind`], gated behind the `serde` feature.
+//!
+//! Scalars are ambiguous once they reach an untagged format like JSON (an
+//! `i8` and a `u32` both just look like a number), so the `Serialize`/
+//! `Deserialize` impls on `ParamKind` itself use an internally-tagged
+//! representation (`{ "type": "int", "value": 1 }`) as the canonical,
+//! round-trippable form. [`pretty`] offers the untagged form as an opt-in
+//! for hand-written files, the same split `plist` draws between its
+//! `Value` encoding and a format's native types.
+#![cfg(feature = "serde")]
+
+use crate::param::{ParamKind, ParamStruct};
+use hash40::Hash40;
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, SerializeStruct, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+const FIELDS: &[&str] = &["type", "value"];
+
+fn type_tag(param: &ParamKind) -> &'static str {
+    match param {
+        ParamKind::Bool(_) => "bool",
+        ParamKind::I8(_) => "sbyte",
+        ParamKind::U8(_) => "byte",
+        ParamKind::I16(_) => "short",
+        ParamKind::U16(_) => "ushort",
+        ParamKind::I32(_) => "int",
+        ParamKind::U32(_) => "uint",
+        ParamKind::Float(_) => "float",
+        ParamKind::Hash(_) => "hash40",
+        ParamKind::Str(_) => "string",
+        ParamKind::List(_) => "list",
+        ParamKind::Struct(_) => "struct",
+    }
+}
+
+impl Serialize for ParamKind {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("ParamKind", 2)?;
+        state.serialize_field("type", type_tag(self))?;
+        match self {
+            ParamKind::Bool(val) => state.serialize_field("value", val)?,
+            ParamKind::I8(val) => state.serialize_field("value", val)?,
+            ParamKind::U8(val) => state.serialize_field("value", val)?,
+            ParamKind::I16(val) => state.serialize_field("value", val)?,
+            ParamKind::U16(val) => state.serialize_field("value", val)?,
+            ParamKind::I32(val) => state.serialize_field("value", val)?,
+            ParamKind::U32(val) => state.serialize_field("value", val)?,
+            ParamKind::Float(val) => state.serialize_field("value", val)?,
+            ParamKind::Hash(val) => state.serialize_field("value", &val.to_string())?,
+            ParamKind::Str(val) => state.serialize_field("value", val)?,
+            ParamKind::List(val) => state.serialize_field("value", val)?,
+            ParamKind::Struct(val) => state.serialize_field("value", &StructMap(val))?,
+        }
+        state.end()
+    }
+}
+
+// `ParamStruct` is an ordered `Vec<(Hash40, ParamKind)>`, not a `HashMap`, so
+// it gets its own `Serialize` that walks the vec in order instead of going
+// through a derived map impl that would collapse duplicate keys.
+struct StructMap<'a>(&'a ParamStruct);
+
+impl<'a> Serialize for StructMap<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (hash, value) in self.0 {
+            map.serialize_entry(&hash.to_string(), value)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for ParamKind {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ParamKindVisitor;
+
+        impl<'de> Visitor<'de> for ParamKindVisitor {
+            type Value = ParamKind;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a { \"type\": ..., \"value\": ... } param representation")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<ParamKind, A::Error> {
+                let mut ty: Option<String> = None;
+                let mut value: Option<ValueSlot> = None;
+
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "type" => ty = Some(map.next_value()?),
+                        "value" => {
+                            let ty = ty.as_deref().ok_or_else(|| {
+                                de::Error::custom("`value` must come after `type`")
+                            })?;
+                            value = Some(match ty {
+                                "bool" => ValueSlot::Bool(map.next_value()?),
+                                "sbyte" => ValueSlot::I8(map.next_value()?),
+                                "byte" => ValueSlot::U8(map.next_value()?),
+                                "short" => ValueSlot::I16(map.next_value()?),
+                                "ushort" => ValueSlot::U16(map.next_value()?),
+                                "int" => ValueSlot::I32(map.next_value()?),
+                                "uint" => ValueSlot::U32(map.next_value()?),
+                                "float" => ValueSlot::Float(map.next_value()?),
+                                "hash40" => {
+                                    let text: String = map.next_value()?;
+                                    ValueSlot::Hash(
+                                        Hash40::from_str(&text)
+                                            .map_err(|_| de::Error::custom("invalid hash40 label"))?,
+                                    )
+                                }
+                                "string" => ValueSlot::Str(map.next_value()?),
+                                "list" => ValueSlot::List(map.next_value()?),
+                                "struct" => {
+                                    let members: Vec<(String, ParamKind)> = map
+                                        .next_value_seed(StructMapSeed)?;
+                                    ValueSlot::Struct(
+                                        members
+                                            .into_iter()
+                                            .map(|(hash, value)| {
+                                                Hash40::from_str(&hash)
+                                                    .map(|hash| (hash, value))
+                                                    .map_err(|_| {
+                                                        de::Error::custom("invalid hash40 label")
+                                                    })
+                                            })
+                                            .collect::<Result<_, _>>()?,
+                                    )
+                                }
+                                other => {
+                                    return Err(de::Error::unknown_variant(other, KNOWN_TYPES))
+                                }
+                            });
+                        }
+                        other => return Err(de::Error::unknown_field(other, FIELDS)),
+                    }
+                }
+
+                match value.ok_or_else(|| de::Error::missing_field("value"))? {
+                    ValueSlot::Bool(v) => Ok(ParamKind::Bool(v)),
+                    ValueSlot::I8(v) => Ok(ParamKind::I8(v)),
+                    ValueSlot::U8(v) => Ok(ParamKind::U8(v)),
+                    ValueSlot::I16(v) => Ok(ParamKind::I16(v)),
+                    ValueSlot::U16(v) => Ok(ParamKind::U16(v)),
+                    ValueSlot::I32(v) => Ok(ParamKind::I32(v)),
+                    ValueSlot::U32(v) => Ok(ParamKind::U32(v)),
+                    ValueSlot::Float(v) => Ok(ParamKind::Float(v)),
+                    ValueSlot::Hash(v) => Ok(ParamKind::Hash(v)),
+                    ValueSlot::Str(v) => Ok(ParamKind::Str(v)),
+                    ValueSlot::List(v) => Ok(ParamKind::List(v)),
+                    ValueSlot::Struct(v) => Ok(ParamKind::Struct(v)),
+                }
+            }
+        }
+
+        deserializer.deserialize_struct("ParamKind", FIELDS, ParamKindVisitor)
+    }
+}
+
+const KNOWN_TYPES: &[&str] = &[
+    "bool", "sbyte", "byte", "short", "ushort", "int", "uint", "float", "hash40", "string",
+    "list", "struct",
+];
+
+enum ValueSlot {
+    Bool(bool),
+    I8(i8),
+    U8(u8),
+    I16(i16),
+    U16(u16),
+    I32(i32),
+    U32(u32),
+    Float(f32),
+    Hash(Hash40),
+    Str(String),
+    List(Vec<ParamKind>),
+    Struct(ParamStruct),
+}
+
+// A `MapAccess` seed that collects `(key, value)` pairs in document order
+// instead of folding them into a `HashMap`, so duplicate keys and ordering
+// survive the round trip the same way `ParamStruct` requires.
+struct StructMapSeed;
+
+impl<'de> de::DeserializeSeed<'de> for StructMapSeed {
+    type Value = Vec<(String, ParamKind)>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        struct OrderedMapVisitor;
+
+        impl<'de> Visitor<'de> for OrderedMapVisitor {
+            type Value = Vec<(String, ParamKind)>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a map of hash40 labels to param values")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut out = Vec::with_capacity(map.size_hint().unwrap_or(0));
+                while let Some((key, value)) = map.next_entry()? {
+                    out.push((key, value));
+                }
+                Ok(out)
+            }
+        }
+
+        deserializer.deserialize_map(OrderedMapVisitor)
+    }
+}
+
+/// The untagged, "pretty" encoding: scalars as their native JSON/YAML
+/// types, `list` as a sequence, `struct` as a map. Opt in with
+/// `pretty::Pretty` when round-tripping through the canonical tagged form
+/// isn't necessary (hand-edited config rather than machine-generated data) —
+/// integers are ambiguous in untagged JSON/YAML, so they always come back
+/// widened to `I32`/`U32` rather than the narrower type they started as.
+pub mod pretty {
+    use super::*;
+
+    /// Wrapper selecting the untagged encoding for `Serialize`/`Deserialize`.
+    pub struct Pretty<T>(pub T);
+
+    impl Serialize for Pretty<&ParamKind> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            match self.0 {
+                ParamKind::Bool(val) => serializer.serialize_bool(*val),
+                ParamKind::I8(val) => serializer.serialize_i8(*val),
+                ParamKind::U8(val) => serializer.serialize_u8(*val),
+                ParamKind::I16(val) => serializer.serialize_i16(*val),
+                ParamKind::U16(val) => serializer.serialize_u16(*val),
+                ParamKind::I32(val) => serializer.serialize_i32(*val),
+                ParamKind::U32(val) => serializer.serialize_u32(*val),
+                ParamKind::Float(val) => serializer.serialize_f32(*val),
+                ParamKind::Hash(val) => serializer.serialize_str(&val.to_string()),
+                ParamKind::Str(val) => serializer.serialize_str(val),
+                ParamKind::List(val) => {
+                    let mut seq = serializer.serialize_seq(Some(val.len()))?;
+                    for child in val {
+                        seq.serialize_element(&Pretty(child))?;
+                    }
+                    seq.end()
+                }
+                ParamKind::Struct(val) => {
+                    let mut map = serializer.serialize_map(Some(val.len()))?;
+                    for (hash, child) in val {
+                        map.serialize_entry(&hash.to_string(), &Pretty(child))?;
+                    }
+                    map.end()
+                }
+            }
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Pretty<ParamKind> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct PrettyVisitor;
+
+            impl<'de> Visitor<'de> for PrettyVisitor {
+                type Value = ParamKind;
+
+                fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    f.write_str("a bool, number, string, sequence, or map")
+                }
+
+                fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+                    Ok(ParamKind::Bool(v))
+                }
+
+                fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+                    i32::try_from(v)
+                        .map(ParamKind::I32)
+                        .map_err(|_| de::Error::custom(format!("{} does not fit in an i32", v)))
+                }
+
+                fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                    if let Ok(v) = i32::try_from(v) {
+                        return Ok(ParamKind::I32(v));
+                    }
+                    u32::try_from(v)
+                        .map(ParamKind::U32)
+                        .map_err(|_| de::Error::custom(format!("{} does not fit in a u32", v)))
+                }
+
+                fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+                    Ok(ParamKind::Float(v as f32))
+                }
+
+                fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                    Ok(ParamKind::Str(v.to_string()))
+                }
+
+                fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                    let mut out = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                    while let Some(Pretty(child)) = seq.next_element()? {
+                        out.push(child);
+                    }
+                    Ok(ParamKind::List(out))
+                }
+
+                fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                    let mut out = Vec::with_capacity(map.size_hint().unwrap_or(0));
+                    while let Some((key, Pretty(value))) = map.next_entry::<String, Pretty<ParamKind>>()? {
+                        let hash = Hash40::from_str(&key)
+                            .map_err(|_| de::Error::custom("invalid hash40 label"))?;
+                        out.push((hash, value));
+                    }
+                    Ok(ParamKind::Struct(out))
+                }
+            }
+
+            deserializer.deserialize_any(PrettyVisitor).map(Pretty)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::pretty::Pretty;
+    use std::str::FromStr;
+
+    fn sample() -> ParamKind {
+        ParamKind::Struct(vec![
+            (Hash40::from_str("0x1").unwrap(), ParamKind::I32(-5)),
+            (
+                Hash40::from_str("0x2").unwrap(),
+                ParamKind::List(vec![ParamKind::Bool(true), ParamKind::Str("hi".to_string())]),
+            ),
+        ])
+    }
+
+    #[test]
+    fn tagged_round_trips_through_json() {
+        let root = sample();
+        let json = serde_json::to_string(&root).unwrap();
+        let roundtripped: ParamKind = serde_json::from_str(&json).unwrap();
+        assert_eq!(roundtripped, root);
+    }
+
+    #[test]
+    fn pretty_round_trips_duplicate_struct_keys() {
+        // ParamStruct preserves duplicate keys and member order; a HashMap-backed
+        // encoding would silently collapse the two `0x1` entries below. Uses
+        // I32 rather than a narrower int type since `Pretty` always widens
+        // integers back to I32/U32 on the way in.
+        let root = ParamKind::Struct(vec![
+            (Hash40::from_str("0x1").unwrap(), ParamKind::I32(1)),
+            (Hash40::from_str("0x1").unwrap(), ParamKind::I32(2)),
+        ]);
+
+        let json = serde_json::to_string(&Pretty(&root)).unwrap();
+        let Pretty(roundtripped) = serde_json::from_str(&json).unwrap();
+        assert_eq!(roundtripped, root);
+    }
+
+    #[test]
+    fn pretty_rejects_out_of_range_integers() {
+        let result: Result<Pretty<ParamKind>, _> = serde_json::from_str("5000000000");
+        assert!(result.is_err());
+    }
+}
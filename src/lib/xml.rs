@@ -1,4 +1,5 @@
 use crate::param::{ParamKind, ParamList, ParamStruct};
+use hash40::Hash40;
 use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
 use quick_xml::{Reader, Writer};
 
@@ -23,6 +24,9 @@ pub enum ReadError {
     ExpectedOpenOrCloseTag(String),
     ExpectedCloseTag(String),
     ExpectedText,
+    /// An open tag was encountered while expecting text or a close tag,
+    /// e.g. a child tag nested inside a scalar param
+    UnexpectedStartTag(String),
 }
 
 // Bad practice to just copy event names?
@@ -59,7 +63,7 @@ impl From<ioError> for ReadError {
     }
 }
 
-impl<'a> From<&'a Expect<'a>> for ReadError {
+impl From<&Expect> for ReadError {
     fn from(f: &Expect) -> Self {
         match f {
             Expect::Struct => Self::ExpectedStructTag,
@@ -88,65 +92,103 @@ pub fn write_xml<W: Write>(param: &ParamStruct, writer: &mut W) -> Result<(), qu
     Ok(())
 }
 
+// Tag names and hashes are captured as owned buffers rather than borrowed
+// from quick-xml's scratch buffer: that buffer is cleared and reused every
+// iteration of the read loop, so nothing from a previous `Event` may be
+// held onto across iterations.
 #[derive(Debug)]
-struct ParamStack<'a> {
+struct ParamStack {
     pub stack: Vec<ParamKind>,
-    pub expect: Expect<'a>,
+    pub tags: Vec<Vec<u8>>,
+    pub hashes: Vec<Option<Hash40>>,
+    pub expect: Expect,
 }
 
-impl<'a> ParamStack<'a> {
-    fn new() -> Self {
-        Self {
-            stack: Vec::new(),
-            expect: Expect::Struct,
-        }
-    }
-
+impl ParamStack {
     fn with_capacity(capacity: usize) -> Self {
         Self {
             stack: Vec::with_capacity(capacity),
+            tags: Vec::with_capacity(capacity),
+            hashes: Vec::with_capacity(capacity),
             expect: Expect::Struct,
         }
     }
 
-    fn push(&mut self, node_name: &'a [u8]) -> Result<(), ReadError> {
-        match self.expect {
+    fn push(&mut self, node_name: &[u8], hash: Option<Hash40>) -> Result<(), ReadError> {
+        let param = match &self.expect {
             Expect::Struct => {
                 if node_name == b"struct" {
-                    self.stack.push(ParamKind::Struct(Default::default()));
-                    Ok(())
+                    ParamKind::Struct(Default::default())
                 } else {
-                    Err(ReadError::ExpectedStructTag)
+                    return Err(ReadError::ExpectedStructTag);
                 }
             }
-            Expect::OpenOrCloseTag(_) => {
-                self.stack.push(
-                    match node_name {
-                        b"bool" => ParamKind::Bool(Default::default()),
-                        b"sbyte" => ParamKind::I8(Default::default()),
-                        b"byte" => ParamKind::U8(Default::default()),
-                        b"short" => ParamKind::I16(Default::default()),
-                        b"ushort" => ParamKind::U16(Default::default()),
-                        b"int" => ParamKind::I32(Default::default()),
-                        b"uint" => ParamKind::U32(Default::default()),
-                        b"float" => ParamKind::Float(Default::default()),
-                        b"hash40" => ParamKind::Hash(Default::default()),
-                        b"string" => ParamKind::Str(Default::default()),
-                        b"list" => ParamKind::List(Default::default()),
-                        b"struct" => ParamKind::Struct(Default::default()),
-                        _ => return Err(ReadError::UnknownOpenTag(
-                            String::from(from_utf8(node_name)?))
-                        ),
-                });
-
-                Ok(())
+            Expect::OpenOrCloseTag(_) => match node_name {
+                b"bool" => ParamKind::Bool(Default::default()),
+                b"sbyte" => ParamKind::I8(Default::default()),
+                b"byte" => ParamKind::U8(Default::default()),
+                b"short" => ParamKind::I16(Default::default()),
+                b"ushort" => ParamKind::U16(Default::default()),
+                b"int" => ParamKind::I32(Default::default()),
+                b"uint" => ParamKind::U32(Default::default()),
+                b"float" => ParamKind::Float(Default::default()),
+                b"hash40" => ParamKind::Hash(Default::default()),
+                b"string" => ParamKind::Str(Default::default()),
+                b"list" => ParamKind::List(Default::default()),
+                b"struct" => ParamKind::Struct(Default::default()),
+                _ => {
+                    return Err(ReadError::UnknownOpenTag(String::from(from_utf8(
+                        node_name,
+                    )?)))
+                }
+            },
+            Expect::Text | Expect::CloseTag(_) => {
+                return Err(ReadError::UnexpectedStartTag(String::from(from_utf8(
+                    node_name,
+                )?)))
             }
-            _ => unreachable!(),
-        }
+        };
+
+        self.expect = match &param {
+            ParamKind::List(_) | ParamKind::Struct(_) => Expect::OpenOrCloseTag(node_name.to_vec()),
+            _ => Expect::Text,
+        };
+
+        self.stack.push(param);
+        self.tags.push(node_name.to_vec());
+        self.hashes.push(hash);
+
+        Ok(())
     }
 
-    fn pop(&mut self, node_name: &[u8]) -> Result<(), ReadError> {
-        unimplemented!()
+    // Pops the completed node named `node_name` and either attaches it to
+    // the now-exposed parent container, or - if the stack is left empty -
+    // returns it as the finished root.
+    fn pop(&mut self, node_name: &[u8]) -> Result<Option<ParamKind>, ReadError> {
+        match self.tags.last() {
+            Some(tag) if tag.as_slice() == node_name => {}
+            _ => {
+                return Err(ReadError::UnmatchedCloseTag(String::from(from_utf8(
+                    node_name,
+                )?)))
+            }
+        }
+
+        let value = self.stack.pop().unwrap();
+        self.tags.pop();
+        let hash = self.hashes.pop().unwrap();
+
+        match self.stack.last_mut() {
+            Some(ParamKind::List(list)) => list.push(value),
+            Some(ParamKind::Struct(fields)) => {
+                fields.push((hash.ok_or(ReadError::MissingHash)?, value))
+            }
+            Some(_) => unreachable!("only list/struct can hold child nodes"),
+            None => return Ok(Some(value)),
+        }
+
+        self.expect = Expect::OpenOrCloseTag(self.tags.last().unwrap().clone());
+        Ok(None)
     }
 
     fn peek(&self) -> &ParamKind {
@@ -159,16 +201,13 @@ impl<'a> ParamStack<'a> {
 
     fn handle_text(&mut self, text: &[u8]) -> Result<(), ReadError> {
         if let Expect::Text = self.expect {
-            let mut top = self.last_mut();
+            let text = from_utf8(text)?;
             macro_rules! convert {
-                ($t:path) => {{
-                    top = &mut FromStr::from_str(from_utf8(text)?)
-                        .map($t)
-                        .or(Err(ReadError::ParseError))?;
-                        Ok(())
+                ($t:path) => {
+                    $t(FromStr::from_str(text).or(Err(ReadError::ParseError))?)
                 };
-            }}
-            match top {
+            }
+            let value = match self.peek() {
                 ParamKind::Bool(_) => convert!(ParamKind::Bool),
                 ParamKind::I8(_) => convert!(ParamKind::I8),
                 ParamKind::U8(_) => convert!(ParamKind::U8),
@@ -178,14 +217,17 @@ impl<'a> ParamStack<'a> {
                 ParamKind::U32(_) => convert!(ParamKind::U32),
                 ParamKind::Float(_) => convert!(ParamKind::Float),
                 ParamKind::Hash(_) => convert!(ParamKind::Hash),
-                ParamKind::Str(_) => convert!(ParamKind::Str),
+                ParamKind::Str(_) => ParamKind::Str(text.to_string()),
                 // Note for readers
                 // Expect is only set to Text after reading a value-type open tag
                 // The two cases below are designed to be impossible
                 ParamKind::List(_) => unreachable!(),
                 ParamKind::Struct(_) => unreachable!(),
-            }
-        } else if text.len() == 0 {
+            };
+            *self.last_mut() = value;
+            self.expect = Expect::CloseTag(self.tags.last().unwrap().clone());
+            Ok(())
+        } else if text.is_empty() {
             // empty text event being sent from quick-xml is meaningless
             Ok(())
         } else {
@@ -196,48 +238,72 @@ impl<'a> ParamStack<'a> {
 
 /// XML Reading state handling
 #[derive(Debug, Clone)]
-pub enum Expect<'a> {
+pub enum Expect {
     /// Should only be used at the start of the file
     Struct,
     /// After reading a list or struct, expects either the close tag
     /// Or any open tag for a new param
-    OpenOrCloseTag(&'a [u8]),
+    OpenOrCloseTag(Vec<u8>),
     /// After parsing a text event out of a value-type param, expects this close tag.
     /// Instead of a stack of strings, this gets set when the stack is changed
-    CloseTag(&'a [u8]),
+    CloseTag(Vec<u8>),
     /// Used for the inside of value-type params
     Text,
 }
 
+// Pulls the `hash` attribute (if any) off of a struct member's open tag.
+fn read_hash_attr(start: &BytesStart) -> Result<Option<Hash40>, ReadError> {
+    for attr in start.attributes() {
+        let attr = attr.or(Err(ReadError::ParseError))?;
+        if attr.key == b"hash" {
+            let value = attr.unescaped_value()?;
+            let text = from_utf8(&value)?;
+            return Hash40::from_str(text)
+                .map(Some)
+                .or(Err(ReadError::ParseError));
+        }
+    }
+    Ok(None)
+}
+
 /// Read a ParamStruct from XML
 pub fn read_xml<R: BufRead>(buf_reader: &mut R) -> Result<ParamStruct, ReadError> {
     let mut reader = Reader::from_reader(buf_reader);
     reader.expand_empty_elements(true);
+    // `write_xml` pretty-prints with indentation, so whitespace-only text
+    // nodes show up between every tag; trim them rather than teaching
+    // `handle_text` to tolerate text while a container is still open.
+    reader.trim_text(true);
     let mut buf = Vec::with_capacity(0x100);
-    let mut stack = Vec::<ParamKind>::with_capacity(0x100);
+    let mut stack = ParamStack::with_capacity(0x100);
 
     loop {
         match reader.read_event(&mut buf)? {
             Event::Start(start) => {
-                // match start.name() {
-
-                // }
+                let hash = read_hash_attr(&start)?;
+                stack.push(start.name(), hash)?;
             }
             Event::Text(text) => {
-
+                stack.handle_text(&text.unescaped()?)?;
             }
             Event::End(end) => {
-
+                if let Some(root) = stack.pop(end.name())? {
+                    return match root {
+                        ParamKind::Struct(fields) => Ok(fields),
+                        _ => unreachable!("the root node is always a struct"),
+                    };
+                }
             }
             Event::Eof => {
-
+                return Err(ReadError::QuickXml(quick_xml::Error::UnexpectedEof(
+                    String::from("reached end of file before the root struct was closed"),
+                )))
             }
-            _ => unimplemented!(),
+            _ => {}
         }
 
         buf.clear();
     }
-    //read_start(&mut xml_reader, &mut buf)
 }
 
 // METHODS FOR WRITING
@@ -325,4 +391,45 @@ fn struct_to_node<W: Write>(
         writer.write_event(Event::End(BytesEnd::borrowed(name)))?;
     }
     Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn sample() -> ParamStruct {
+        vec![
+            (Hash40::from_str("0x10").unwrap(), ParamKind::U16(7)),
+            (
+                Hash40::from_str("0x20").unwrap(),
+                ParamKind::List(vec![
+                    ParamKind::Hash(Hash40::from_str("0x30").unwrap()),
+                    ParamKind::Str("xml param".to_string()),
+                ]),
+            ),
+            (
+                Hash40::from_str("0x40").unwrap(),
+                ParamKind::Struct(vec![(Hash40::from_str("0x50").unwrap(), ParamKind::Bool(false))]),
+            ),
+        ]
+    }
+
+    #[test]
+    fn round_trips_through_write_and_read() {
+        let root = sample();
+
+        let mut xml = Vec::new();
+        write_xml(&root, &mut xml).unwrap();
+
+        let roundtripped = read_xml(&mut xml.as_slice()).unwrap();
+        assert_eq!(roundtripped, root);
+    }
+
+    #[test]
+    fn rejects_a_tag_nested_inside_a_scalar_instead_of_panicking() {
+        let xml = b"<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<struct><int hash=\"0x1\">5<bogus/></int></struct>";
+        let result = read_xml(&mut &xml[..]);
+        assert!(matches!(result, Err(ReadError::UnexpectedStartTag(_))));
+    }
 }
\ No newline at end of file